@@ -0,0 +1,133 @@
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::error::Error;
+use std::fmt::Debug;
+
+/// How `Finished with` renders the error of a failed task
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// `error = ?err`, the default
+    Debug,
+    /// `error = %err`, just the outermost message
+    Display,
+    /// the whole chain on one line, `outer: cause: root cause`, same as anyhow's `{:#}`
+    Alternate,
+    /// outermost message in `error` and one entry per cause in `error.chain`, tracing has no array values
+    /// so the list is recorded as a single string, its Debug rendering `["outer", "cause"]`, which a JSON layer
+    /// emits as a string rather than an array it could index
+    Chain,
+    /// `error` recorded as a `&dyn Error` value, subscribers get it through `Visit::record_error`
+    /// and can walk `source()` themselves
+    Structured
+}
+
+/// Gives `ErrorFormat` access to the source chain and backtrace of a task error
+///
+/// Implemented for every `std::error::Error`, for `anyhow::Error` and for boxed errors, `M` only tells
+/// those impls apart and is always inferred
+pub trait TaskError<M> {
+    fn as_error(&self) -> &(dyn Error + 'static);
+
+    fn backtrace(&self) -> Option<&Backtrace> {
+        None
+    }
+}
+
+pub enum StdError {}
+pub enum BoxedError {}
+#[cfg(feature = "anyhow")]
+pub enum AnyhowError {}
+
+impl<E: Error + 'static> TaskError<StdError> for E {
+    fn as_error(&self) -> &(dyn Error + 'static) {
+        self
+    }
+}
+
+impl TaskError<BoxedError> for Box<dyn Error + Send + Sync + 'static> {
+    fn as_error(&self) -> &(dyn Error + 'static) {
+        &**self
+    }
+}
+
+impl TaskError<BoxedError> for Box<dyn Error + 'static> {
+    fn as_error(&self) -> &(dyn Error + 'static) {
+        &**self
+    }
+}
+
+#[cfg(feature = "anyhow")]
+impl TaskError<AnyhowError> for anyhow::Error {
+    fn as_error(&self) -> &(dyn Error + 'static) {
+        &**self
+    }
+
+    fn backtrace(&self) -> Option<&Backtrace> {
+        Some(anyhow::Error::backtrace(self))
+    }
+}
+
+/// `outer: cause: root cause`, an error and its sources on one line
+pub(crate) fn chain_line(error: &(dyn Error + 'static)) -> String {
+    chain(error).join(": ")
+}
+
+fn chain(error: &(dyn Error + 'static)) -> Vec<String> {
+    let mut chain = vec![];
+    let mut source = Some(error);
+    while let Some(error) = source {
+        chain.push(error.to_string());
+        source = error.source();
+    }
+    chain
+}
+
+type ErrorView<'a> = (&'a (dyn Error + 'static), Option<&'a Backtrace>);
+
+pub(crate) struct ErrorReport<O> {
+    pub(crate) format: ErrorFormat,
+    pub(crate) backtrace: bool,
+    view: fn(&O) -> Option<ErrorView<'_>>
+}
+
+pub(crate) struct Rendered<'a> {
+    pub(crate) message: String,
+    /// one line rendering for the span, the chain whatever the format unless there's no error view
+    pub(crate) summary: String,
+    pub(crate) error: Option<&'a (dyn Error + 'static)>,
+    pub(crate) chain: Option<Vec<String>>,
+    pub(crate) backtrace: Option<&'a Backtrace>
+}
+
+impl<R, E> ErrorReport<Result<R, E>> {
+    pub(crate) fn new<M>() -> ErrorReport<Result<R, E>> where E: TaskError<M> {
+        ErrorReport {
+            format: ErrorFormat::Debug,
+            backtrace: false,
+            view: |r| r.as_ref().err().map(|err| (err.as_error(), err.backtrace()))
+        }
+    }
+}
+
+impl<O> ErrorReport<O> {
+    pub(crate) fn render<'a>(&self, output: &'a O, err: &dyn Debug) -> Rendered<'a> {
+        let (error, backtrace) = match (self.view)(output) {
+            Some(view) => view,
+            None => {
+                let message = format!("{:?}", err);
+                return Rendered { summary: message.clone(), message, error: None, chain: None, backtrace: None };
+            }
+        };
+
+        let summary = chain_line(error);
+        let (message, chain) = match self.format {
+            ErrorFormat::Debug => (format!("{:?}", err), None),
+            ErrorFormat::Display => (error.to_string(), None),
+            ErrorFormat::Alternate => (summary.clone(), None),
+            ErrorFormat::Chain => (error.to_string(), Some(chain(error))),
+            ErrorFormat::Structured => (error.to_string(), None)
+        };
+        let error = (self.format == ErrorFormat::Structured).then_some(error);
+        let backtrace = backtrace.filter(|bt| self.backtrace && bt.status() == BacktraceStatus::Captured);
+        Rendered { message, summary, error, chain, backtrace }
+    }
+}
@@ -0,0 +1,49 @@
+use std::task::Context;
+use std::time::{Duration, Instant};
+
+/// Emits `Still running` events every `interval` while the task is alive
+///
+/// Without the `tokio` feature heartbeats are only checked when the task gets polled, so a task nobody wakes up
+/// stays silent, with it a timer wakes the task up on every interval when it's polled on a tokio runtime (which needs
+/// time enabled), elsewhere it falls back to checking on polls
+pub(crate) struct Heartbeat {
+    interval: Duration,
+    next: Option<Instant>,
+    #[cfg(feature = "tokio")]
+    timer: Option<std::pin::Pin<Box<tokio::time::Sleep>>>
+}
+
+impl Heartbeat {
+    pub(crate) fn new(interval: Duration) -> Heartbeat {
+        Heartbeat {
+            interval,
+            next: None,
+            #[cfg(feature = "tokio")]
+            timer: None
+        }
+    }
+
+    /// Returns true when a heartbeat is due
+    pub(crate) fn tick(&mut self, started: Instant, cx: &mut Context<'_>) -> bool {
+        let now = crate::timeout::now();
+        let next = *self.next.get_or_insert(started + self.interval);
+        let due = now >= next;
+        if due {
+            self.next = Some(now + self.interval);
+        }
+
+        #[cfg(feature = "tokio")]
+        if self.timer.is_some() || tokio::runtime::Handle::try_current().is_ok() {
+            let next = self.next.unwrap_or(next).into();
+            let timer = self.timer.get_or_insert_with(|| Box::pin(tokio::time::sleep_until(next)));
+            if timer.deadline() != next {
+                timer.as_mut().reset(next);
+            }
+            let _ = std::future::Future::poll(timer.as_mut(), cx);
+        }
+        #[cfg(not(feature = "tokio"))]
+        let _ = cx;
+
+        due
+    }
+}
@@ -0,0 +1,83 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tracing::Level;
+
+/// Levels `InstrumentedTask` logs its start, success, failure and cancellation events at
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Levels {
+    pub start: Level,
+    pub ok: Level,
+    pub err: Level,
+    pub cancel: Level
+}
+
+impl Levels {
+    pub const fn new(start: Level, ok: Level, err: Level) -> Levels {
+        Levels { start, ok, err, cancel: Level::WARN }
+    }
+
+    pub const fn with_cancel(self, cancel: Level) -> Levels {
+        Levels { cancel, ..self }
+    }
+}
+
+impl Default for Levels {
+    fn default() -> Levels {
+        default_levels()
+    }
+}
+
+static DEFAULT_START: AtomicUsize = AtomicUsize::new(2);
+static DEFAULT_OK: AtomicUsize = AtomicUsize::new(2);
+static DEFAULT_ERR: AtomicUsize = AtomicUsize::new(0);
+static DEFAULT_CANCEL: AtomicUsize = AtomicUsize::new(1);
+
+/// Changes crate-wide levels used by tasks which don't set their own
+pub fn set_default_levels(levels: Levels) {
+    DEFAULT_START.store(to_index(levels.start), Ordering::Relaxed);
+    DEFAULT_OK.store(to_index(levels.ok), Ordering::Relaxed);
+    DEFAULT_ERR.store(to_index(levels.err), Ordering::Relaxed);
+    DEFAULT_CANCEL.store(to_index(levels.cancel), Ordering::Relaxed);
+}
+
+pub fn default_levels() -> Levels {
+    Levels {
+        start: from_index(DEFAULT_START.load(Ordering::Relaxed)),
+        ok: from_index(DEFAULT_OK.load(Ordering::Relaxed)),
+        err: from_index(DEFAULT_ERR.load(Ordering::Relaxed)),
+        cancel: from_index(DEFAULT_CANCEL.load(Ordering::Relaxed))
+    }
+}
+
+fn to_index(level: Level) -> usize {
+    match level {
+        Level::ERROR => 0,
+        Level::WARN => 1,
+        Level::INFO => 2,
+        Level::DEBUG => 3,
+        _ => 4
+    }
+}
+
+fn from_index(index: usize) -> Level {
+    match index {
+        0 => Level::ERROR,
+        1 => Level::WARN,
+        2 => Level::INFO,
+        3 => Level::DEBUG,
+        _ => Level::TRACE
+    }
+}
+
+// tracing needs the level of an event to be known at compile time, so dispatch to a callsite per level
+macro_rules! event_at {
+    ($level:expr, $($args:tt)+) => {
+        match $level {
+            tracing::Level::ERROR => tracing::error!($($args)+),
+            tracing::Level::WARN => tracing::warn!($($args)+),
+            tracing::Level::INFO => tracing::info!($($args)+),
+            tracing::Level::DEBUG => tracing::debug!($($args)+),
+            _ => tracing::trace!($($args)+)
+        }
+    };
+}
@@ -1,84 +1,42 @@
-use std::{pin::Pin, time::Instant};
+use std::pin::Pin;
 use std::future::Future;
 
-use tracing::{info, error, span::{Span}};
-use tracing_futures::Instrument;
+mod error;
+#[macro_use]
+mod level;
+mod heartbeat;
+mod name;
+mod outcome;
+mod panic;
+#[cfg(feature = "tokio")]
+mod retry;
+mod stats;
+mod task;
+mod timeout;
 
-type Result<T> = anyhow::Result<T>;
-pub type TaskFut<'a, T=()> = Pin<Box<dyn Future<Output=Result<T>> + 'a>>;
+pub use error::{ErrorFormat, TaskError};
+pub use level::{Levels, set_default_levels, default_levels};
+pub use name::{NameStyle, clean_fn, clean_fn_with, set_default_name_style, default_name_style};
+#[doc(hidden)]
+pub use name::CallsiteName;
+pub use outcome::{Outcome, Status};
+pub use panic::Panicked;
+#[cfg(feature = "tokio")]
+pub use retry::{Backoff, FixedBackoff, ExponentialBackoff, Jitter, Retry};
+pub use task::{TracingTask, InstrumentedTask};
+#[cfg(feature = "tokio")]
+pub use timeout::TimedOut;
+#[cfg(feature = "macros")]
+pub use tracing_tools_macros::traced_task;
 
-pub struct TracingTask<'a, R=()> {
-    span: Span,
-    future: TaskFut<'a, R>,
-    is_long_lived: bool
-}
-
-impl<'a, R> TracingTask<'a, R> {
-    pub fn new<T: Future<Output=Result<R>> + 'a>(span: Span, fut: T) -> TracingTask<'a, R> {
-        TracingTask {
-            span,
-            future: Box::pin(fut),
-            is_long_lived: true
-        }
-    }
-
-    pub fn new_short_lived<T: Future<Output=Result<R>> + 'a>(span: Span, fut: T) -> TracingTask<'a, R> {
-        TracingTask {
-            span,
-            future: Box::pin(fut),
-            is_long_lived: false
-        }
-    }
-}
-
-impl<'a, R: 'a> TracingTask<'a, R> {
-    pub fn instrument(self) -> TaskFut<'a, R> {
-        let span = self.span;
-        let future = self.future;
-        let is_long_lived = self.is_long_lived;
-
-        let fut_wrap = async move {
-            if is_long_lived {
-                info!("Starting...");
-            }
-            let t = Instant::now();
-
-            let r = future.await;
-            if r.is_err() {
-                let err = r.err().unwrap();
-                error!(error = ?err, elapsed = ?t.elapsed(), "Finished with");
-                return Err(err);
-            }
-            info!(elapsed = ?t.elapsed(), "Finished [OK]...");
-            Ok(r.unwrap())
-        };
-
-        Box::pin(fut_wrap.instrument(span))
-    }
-}
+#[cfg(feature = "anyhow")]
+pub type Error = anyhow::Error;
+#[cfg(not(feature = "anyhow"))]
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
 
-pub fn clean_fn(s: &str) -> String {
-    let s = String::from(s);
-    let name = s.split("::")
-        .collect::<Vec<&str>>()
-        .into_iter().rev()
-        .take(2).rev()
-        .collect::<Vec<&str>>()
-        .join("::");
-
-    let mut final_name = String::from("");
-    let mut skip = 0;
-    for c in name.chars() {
-        if c == '<' {
-            skip += 1;
-        } else if c == '>' {
-            skip -= 1;
-        } else if skip < 1 {
-            final_name.push(c);
-        }
-    }
-    final_name
-}
+type Result<T, E=Error> = std::result::Result<T, E>;
+pub type TaskFut<'a, T=(), E=Error> = Pin<Box<dyn Future<Output=Result<T, E>> + 'a>>;
+pub type SendTaskFut<'a, T=(), E=Error> = Pin<Box<dyn Future<Output=Result<T, E>> + Send + 'a>>;
 
 #[macro_export]
 macro_rules! function {
@@ -92,15 +50,125 @@ macro_rules! function {
     }}
 }
 
+/// Creates a task span, accepts an optional leading level (`ERROR` by default, so task spans are never
+/// filtered out under error events), an optional explicit name literal (defaults to the calling function)
+/// and any trailing fields `tracing::span!` would accept
+///
+/// `span!()`, `span!(n = n)`, `span!(Level::INFO, n = n)`, `span!("fetch", n = n)`, `span!(Level::INFO, "fetch", n = n)`
+///
+/// The calling function is rendered with the default `NameStyle`, a leading `name_style: NameStyle::Full` overrides
+/// it for a single call
+///
+/// The span itself is named after the explicit name or, without one, after the calling module and line, `my_crate::db:42`
+/// (span names have to be static), while the `name` field keeps the calling function
+///
+/// `outcome`, `elapsed_ms`, `error.message` and `otel.status_code` are declared empty, the task records them once it's done
+///
+/// With the `location` feature the span also gets `file`, `line` and `module` fields of the call site
+///
+/// With the `opentelemetry` feature the span also gets `otel.name` (same as `name`) and `otel.kind = "internal"`,
+/// record `otel.kind` to change the latter
 #[macro_export]
 macro_rules! span {
-    ($($tts:tt)*) => {
-        tracing::span!(tracing::Level::ERROR, "task", name = $crate::clean_fn($crate::function!()).as_str(), $($tts)*);
+    (@callsite $callsite:expr, $style:expr, $lvl:expr $(, $($fields:tt)*)?) => {
+        $crate::__task_span!($lvl, $callsite, {
+            static NAME: $crate::CallsiteName = $crate::CallsiteName::new();
+            NAME.get($style, $crate::function!())
+        } $(, $($fields)*)?)
+    };
+    (@styled $style:expr, $lvl:expr, $name:literal $(, $($fields:tt)*)?) => {
+        $crate::__task_span!($lvl, $name, $name $(, $($fields)*)?)
+    };
+    (@styled $style:expr, $lvl:expr $(, $($fields:tt)*)?) => {
+        $crate::span!(@callsite concat!(module_path!(), ":", line!()), $style, $lvl $(, $($fields)*)?)
+    };
+    (@level $lvl:expr $(, $($rest:tt)*)?) => {
+        $crate::span!(@styled $crate::default_name_style(), $lvl $(, $($rest)*)?)
     };
-    ($name:expr) => {
-        tracing::span!(tracing::Level::ERROR, "task", name = $name);
+    (name_style: $style:expr, Level::$lvl:ident $(, $($rest:tt)*)?) => {
+        $crate::span!(@styled $style, tracing::Level::$lvl $(, $($rest)*)?)
+    };
+    (name_style: $style:expr, tracing::Level::$lvl:ident $(, $($rest:tt)*)?) => {
+        $crate::span!(@styled $style, tracing::Level::$lvl $(, $($rest)*)?)
+    };
+    (name_style: $style:expr $(, $($rest:tt)*)?) => {
+        $crate::span!(@styled $style, tracing::Level::ERROR $(, $($rest)*)?)
+    };
+    (Level::$lvl:ident $(, $($rest:tt)*)?) => {
+        $crate::span!(@level tracing::Level::$lvl $(, $($rest)*)?)
+    };
+    (tracing::Level::$lvl:ident $(, $($rest:tt)*)?) => {
+        $crate::span!(@level tracing::Level::$lvl $(, $($rest)*)?)
     };
     () => {
-        tracing::span!(tracing::Level::ERROR, "task", name = $crate::clean_fn($crate::function!()).as_str());
+        $crate::span!(@level tracing::Level::ERROR)
+    };
+    ($($rest:tt)+) => {
+        $crate::span!(@level tracing::Level::ERROR, $($rest)+)
     };
-}
\ No newline at end of file
+}
+
+// defined per feature, a #[cfg] inside of span! would be evaluated in the calling crate
+#[cfg(not(feature = "opentelemetry"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __task_span {
+    ($lvl:expr, $callsite:expr, $name:expr $(, $($fields:tt)*)?) => {
+        $crate::__located_span!($lvl, $callsite, name = $name, outcome = tracing::field::Empty, elapsed_ms = tracing::field::Empty,
+            error.message = tracing::field::Empty, otel.status_code = tracing::field::Empty $(, $($fields)*)?)
+    };
+}
+
+#[cfg(feature = "opentelemetry")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __task_span {
+    ($lvl:expr, $callsite:expr, $name:expr $(, $($fields:tt)*)?) => {
+        $crate::__located_span!($lvl, $callsite, name = $name, otel.name = $name, otel.kind = "internal",
+            outcome = tracing::field::Empty, elapsed_ms = tracing::field::Empty, error.message = tracing::field::Empty,
+            otel.status_code = tracing::field::Empty, otel.status_description = tracing::field::Empty $(, $($fields)*)?)
+    };
+}
+
+#[cfg(not(feature = "location"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __located_span {
+    ($lvl:expr, $callsite:expr, $($fields:tt)*) => {
+        tracing::span!($lvl, $callsite, $($fields)*)
+    };
+}
+
+// file!() and line!() expand to the outermost macro call, i.e. the span! call site
+#[cfg(feature = "location")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __located_span {
+    ($lvl:expr, $callsite:expr, $($fields:tt)*) => {
+        tracing::span!($lvl, $callsite, file = file!(), line = line!(), module = module_path!(), $($fields)*)
+    };
+}
+
+macro_rules! level_span {
+    ($d:tt $macro_name:ident, $lvl:ident) => {
+        /// Same as `span!` with the level fixed, mirrors the `tracing` macro of the same name
+        #[macro_export]
+        macro_rules! $macro_name {
+            () => {
+                $crate::span!(@level tracing::Level::$lvl)
+            };
+            (name_style: $d style:expr $d(, $d($d rest:tt)*)?) => {
+                $crate::span!(@styled $d style, tracing::Level::$lvl $d(, $d($d rest)*)?)
+            };
+            ($d($d rest:tt)+) => {
+                $crate::span!(@level tracing::Level::$lvl, $d($d rest)+)
+            };
+        }
+    };
+}
+
+level_span!($ error_span, ERROR);
+level_span!($ warn_span, WARN);
+level_span!($ info_span, INFO);
+level_span!($ debug_span, DEBUG);
+level_span!($ trace_span, TRACE);
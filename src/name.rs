@@ -0,0 +1,97 @@
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// How `span!` renders the `function!()` path of the calling function into the `name` field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NameStyle {
+    /// last two segments, `module::function` or `Type::method`, the default
+    Short,
+    /// the whole path, `my_crate::module::Type::method`
+    Full,
+    /// the path relative to the crate root, `module::Type::method`
+    Relative,
+    /// just the function, `method`
+    Function,
+    /// `Type::method` for methods and only the function otherwise, segments not looking like a snake_case module
+    /// are taken for types
+    Method
+}
+
+const STYLES: [NameStyle; 5] = [NameStyle::Short, NameStyle::Full, NameStyle::Relative, NameStyle::Function, NameStyle::Method];
+
+static DEFAULT_STYLE: AtomicUsize = AtomicUsize::new(0);
+
+/// Changes the crate-wide style used by `span!` calls which don't set their own
+pub fn set_default_name_style(style: NameStyle) {
+    DEFAULT_STYLE.store(style as usize, Ordering::Relaxed);
+}
+
+pub fn default_name_style() -> NameStyle {
+    STYLES[DEFAULT_STYLE.load(Ordering::Relaxed)]
+}
+
+/// Same as `clean_fn_with(NameStyle::Short, s)`, `crate::module::Foo<T>::bar::{{closure}}` becomes `Foo::bar`
+pub fn clean_fn(s: &str) -> String {
+    clean_fn_with(NameStyle::Short, s)
+}
+
+/// Renders a `function!()` path in the given style
+///
+/// Generics and closure / async block segments are dropped, `<Foo as Bar>::baz` is treated as `Foo::baz`
+pub fn clean_fn_with(style: NameStyle, s: &str) -> String {
+    let path = strip_generics(s);
+    let segments = path.split("::")
+        .filter(|segment| !matches!(*segment, "{{closure}}" | "{{async_block}}" | "{{async_fn_body}}"))
+        .collect::<Vec<&str>>();
+    let is_type = |segment: &str| !segment.starts_with(|c: char| c.is_lowercase() || c == '_');
+    let skip = match style {
+        NameStyle::Short => segments.len().saturating_sub(2),
+        NameStyle::Full => 0,
+        NameStyle::Relative => if segments.len() > 1 { 1 } else { 0 },
+        NameStyle::Function => segments.len().saturating_sub(1),
+        NameStyle::Method => match segments.len() {
+            n if n > 1 && is_type(segments[n - 2]) => n - 2,
+            n => n.saturating_sub(1)
+        }
+    };
+    segments[skip..].join("::")
+}
+
+// drops everything in between of `<` and `>` except for the type of a qualified path, `<Foo<T> as Bar>` becomes `Foo`
+fn strip_generics(s: &str) -> String {
+    let mut name = String::with_capacity(s.len());
+    // one entry per open `<`, true while its content is kept
+    let mut keep = vec![];
+    for (i, c) in s.char_indices() {
+        match c {
+            '<' => keep.push(keep.iter().all(|&k| k) && (i == 0 || s[..i].ends_with("::"))),
+            '>' => { keep.pop(); },
+            ' ' if s[i..].starts_with(" as ") && keep.last() == Some(&true) => {
+                if let Some(k) = keep.last_mut() {
+                    *k = false;
+                }
+            },
+            _ if keep.iter().all(|&k| k) => name.push(c),
+            _ => {}
+        }
+    }
+    name
+}
+
+/// Name of a `span!` call site, `clean_fn_with` runs once per style on the first span instead of on every one of them
+#[doc(hidden)]
+#[derive(Default)]
+pub struct CallsiteName([OnceLock<String>; STYLES.len()]);
+
+impl CallsiteName {
+    pub const fn new() -> CallsiteName {
+        // a named constant rather than an inline `const {}` block, which would need Rust 1.79
+        #[allow(clippy::declare_interior_mutable_const)]
+        const INIT: OnceLock<String> = OnceLock::new();
+        CallsiteName([INIT; STYLES.len()])
+    }
+
+    pub fn get(&'static self, style: NameStyle, function: &str) -> &'static str {
+        self.0[style as usize].get_or_init(|| clean_fn_with(style, function))
+    }
+}
@@ -0,0 +1,37 @@
+use std::fmt::Debug;
+
+/// What a finished task resolved to, as seen by `InstrumentedTask` when picking the event to log
+pub enum Status<'a> {
+    Ok,
+    None,
+    Err(&'a dyn Debug)
+}
+
+/// Implement for your own return types to be able to wrap futures resolving to them with `TracingTask`
+pub trait Outcome {
+    fn status(&self) -> Status<'_>;
+}
+
+impl<T, E: Debug> Outcome for Result<T, E> {
+    fn status(&self) -> Status<'_> {
+        match self {
+            Ok(_) => Status::Ok,
+            Err(err) => Status::Err(err)
+        }
+    }
+}
+
+impl<T> Outcome for Option<T> {
+    fn status(&self) -> Status<'_> {
+        match self {
+            Some(_) => Status::Ok,
+            None => Status::None
+        }
+    }
+}
+
+impl Outcome for () {
+    fn status(&self) -> Status<'_> {
+        Status::Ok
+    }
+}
@@ -0,0 +1,39 @@
+use std::any::Any;
+use std::error::Error;
+use std::fmt::{self, Display};
+
+/// Error `TracingTask::catch_panics` turns a panic of the wrapped future into
+#[derive(Debug)]
+pub struct Panicked {
+    message: String
+}
+
+impl Panicked {
+    pub(crate) fn new(payload: &(dyn Any + Send)) -> Panicked {
+        let message = if let Some(s) = payload.downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = payload.downcast_ref::<String>() {
+            s.clone()
+        } else {
+            String::from("Box<dyn Any>")
+        };
+        Panicked { message }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for Panicked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "task panicked: {}", self.message)
+    }
+}
+
+impl Error for Panicked {}
+
+pub(crate) enum OnPanic<O> {
+    Resume,
+    Convert(fn(Panicked) -> O)
+}
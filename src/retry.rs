@@ -0,0 +1,260 @@
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::fmt::Debug;
+use std::time::{Duration, Instant};
+
+use pin_project_lite::pin_project;
+use tokio::time::{sleep, Sleep};
+use tracing::{Level, span::Span};
+
+use crate::{ErrorFormat, Outcome, Status, TracingTask};
+use crate::error::{ErrorReport, TaskError};
+use crate::timeout::now;
+
+/// Decides whether a failed attempt is retried and after what delay
+pub trait Backoff {
+    /// Delay before the next attempt after `attempt` (starting from 1) failed, `None` gives up
+    fn next_delay(&mut self, attempt: u32) -> Option<Duration>;
+}
+
+impl<T: FnMut(u32) -> Option<Duration>> Backoff for T {
+    fn next_delay(&mut self, attempt: u32) -> Option<Duration> {
+        self(attempt)
+    }
+}
+
+/// Same delay between up to `attempts` attempts
+#[derive(Clone, Copy, Debug)]
+pub struct FixedBackoff {
+    delay: Duration,
+    attempts: u32
+}
+
+impl FixedBackoff {
+    pub fn new(delay: Duration, attempts: u32) -> FixedBackoff {
+        FixedBackoff { delay, attempts }
+    }
+}
+
+impl Backoff for FixedBackoff {
+    fn next_delay(&mut self, attempt: u32) -> Option<Duration> {
+        if attempt >= self.attempts {
+            return None;
+        }
+        Some(self.delay)
+    }
+}
+
+/// Delay multiplied by `factor` (2 by default) after every attempt, capped by `max_delay`
+#[derive(Clone, Copy, Debug)]
+pub struct ExponentialBackoff {
+    initial: Duration,
+    factor: u32,
+    max_delay: Duration,
+    attempts: u32
+}
+
+impl ExponentialBackoff {
+    pub fn new(initial: Duration, attempts: u32) -> ExponentialBackoff {
+        ExponentialBackoff { initial, factor: 2, max_delay: Duration::MAX, attempts }
+    }
+
+    pub fn factor(mut self, factor: u32) -> ExponentialBackoff {
+        self.factor = factor;
+        self
+    }
+
+    pub fn max_delay(mut self, max_delay: Duration) -> ExponentialBackoff {
+        self.max_delay = max_delay;
+        self
+    }
+}
+
+impl Backoff for ExponentialBackoff {
+    fn next_delay(&mut self, attempt: u32) -> Option<Duration> {
+        if attempt >= self.attempts {
+            return None;
+        }
+        let delay = self.factor.checked_pow(attempt.saturating_sub(1))
+            .and_then(|multiplier| self.initial.checked_mul(multiplier))
+            .unwrap_or(self.max_delay);
+        Some(delay.min(self.max_delay))
+    }
+}
+
+/// Randomizes delays of the wrapped policy between half and the full delay, so retrying clients don't sync up
+#[derive(Clone, Debug)]
+pub struct Jitter<B> {
+    inner: B,
+    seed: u64
+}
+
+impl<B: Backoff> Jitter<B> {
+    pub fn new(inner: B) -> Jitter<B> {
+        let seed = RandomState::new().build_hasher().finish() | 1;
+        Jitter { inner, seed }
+    }
+
+    // xorshift64*, good enough to spread delays without pulling in rand
+    fn next_random(&mut self) -> u64 {
+        self.seed ^= self.seed >> 12;
+        self.seed ^= self.seed << 25;
+        self.seed ^= self.seed >> 27;
+        self.seed.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+impl<B: Backoff> Backoff for Jitter<B> {
+    fn next_delay(&mut self, attempt: u32) -> Option<Duration> {
+        let delay = self.inner.next_delay(attempt)?;
+        let half = delay / 2;
+        let nanos = half.as_nanos().min(u64::MAX as u128) as u64;
+        let jitter = if nanos == 0 { 0 } else { self.next_random() % (nanos + 1) };
+        Some(half + Duration::from_nanos(jitter))
+    }
+}
+
+pin_project! {
+    /// Future behind `TracingTask::retry`, runs attempts in child `attempt` spans of the task span
+    pub struct Retry<B, M, Fut>
+    where
+        Fut: Future
+    {
+        backoff: B,
+        make: M,
+        attempt: u32,
+        err_level: Level,
+        report: ErrorReport<Fut::Output>,
+        #[pin]
+        state: RetryState<Fut>
+    }
+}
+
+pin_project! {
+    #[project = RetryStateProj]
+    enum RetryState<Fut> {
+        Idle,
+        Running {
+            #[pin]
+            future: Fut,
+            span: Span,
+            started: Instant
+        },
+        Sleeping {
+            #[pin]
+            sleep: Sleep
+        }
+    }
+}
+
+impl<B, M, Fut, R, E> TracingTask<Retry<B, M, Fut>>
+where
+    B: Backoff,
+    M: FnMut() -> Fut,
+    Fut: Future<Output=Result<R, E>>,
+    E: Debug
+{
+    /// Runs futures produced by `make` until one doesn't fail or `backoff` gives up, each attempt gets
+    /// its own child span with the `attempt` number and failures are logged at `WARN` (see `attempt_err_level`)
+    /// as a one line error chain (see `attempt_error_format`), the final outcome is logged on `span` as usual
+    ///
+    /// Unlike `TracingTask::new`, which takes any `Debug` error, the error has to implement `TaskError`
+    /// (`std::error::Error`, `anyhow::Error` or a boxed error) for the chain to be available
+    pub fn retry<K>(span: Span, backoff: B, make: M) -> TracingTask<Retry<B, M, Fut>> where E: TaskError<K> {
+        let mut report = ErrorReport::new();
+        report.format = ErrorFormat::Alternate;
+        TracingTask::new(span, Retry {
+            backoff,
+            make,
+            attempt: 0,
+            err_level: Level::WARN,
+            report,
+            state: RetryState::Idle
+        })
+    }
+}
+
+impl<B, M, Fut> TracingTask<Retry<B, M, Fut>>
+where
+    B: Backoff,
+    M: FnMut() -> Fut,
+    Fut: Future,
+    Fut::Output: Outcome
+{
+    pub fn attempt_err_level(mut self, level: Level) -> TracingTask<Retry<B, M, Fut>> {
+        self.future_mut().err_level = level;
+        self
+    }
+
+    /// Changes how `Attempt failed` renders the error, `ErrorFormat::Alternate` by default
+    pub fn attempt_error_format(mut self, format: ErrorFormat) -> TracingTask<Retry<B, M, Fut>> {
+        self.future_mut().report.format = format;
+        self
+    }
+}
+
+impl<B, M, Fut> Future for Retry<B, M, Fut>
+where
+    B: Backoff,
+    M: FnMut() -> Fut,
+    Fut: Future,
+    Fut::Output: Outcome
+{
+    type Output = Fut::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        loop {
+            let mut this = self.as_mut().project();
+            match this.state.as_mut().project() {
+                RetryStateProj::Idle => {
+                    *this.attempt += 1;
+                    let span = tracing::span!(Level::ERROR, "attempt", attempt = *this.attempt);
+                    let future = {
+                        let _enter = span.enter();
+                        (this.make)()
+                    };
+                    this.state.set(RetryState::Running { future, span, started: now() });
+                }
+                RetryStateProj::Running { future, span, started } => {
+                    let _enter = span.enter();
+                    let r = match future.poll(cx) {
+                        Poll::Ready(r) => r,
+                        Poll::Pending => return Poll::Pending
+                    };
+                    let delay = match r.status() {
+                        Status::Err(err) => {
+                            let delay = this.backoff.next_delay(*this.attempt);
+                            let rendered = this.report.render(&r, err);
+                            let (elapsed, retry_in) = (now() - *started, delay.map(tracing::field::debug));
+                            match rendered.error {
+                                Some(error) => event_at!(*this.err_level, error, elapsed = ?elapsed, retry_in,
+                                    "Attempt failed"),
+                                None => event_at!(*this.err_level, error = %rendered.message,
+                                    error.chain = rendered.chain.as_ref().map(tracing::field::debug), elapsed = ?elapsed,
+                                    retry_in, "Attempt failed")
+                            }
+                            delay
+                        },
+                        _ => return Poll::Ready(r)
+                    };
+                    match delay {
+                        Some(delay) => {
+                            drop(_enter);
+                            this.state.set(RetryState::Sleeping { sleep: sleep(delay) });
+                        }
+                        None => return Poll::Ready(r)
+                    }
+                }
+                RetryStateProj::Sleeping { sleep } => {
+                    match sleep.poll(cx) {
+                        Poll::Ready(()) => this.state.set(RetryState::Idle),
+                        Poll::Pending => return Poll::Pending
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use std::time::{Duration, Instant};
+
+/// Time spent inside of `poll` of the wrapped future, the rest of the task lifetime it was idle
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct PollStats {
+    pub(crate) busy: Duration,
+    pub(crate) last: Duration,
+    pub(crate) polls: u64
+}
+
+impl PollStats {
+    pub(crate) fn record<T>(&mut self, poll: impl FnOnce() -> T) -> T {
+        // counted upfront, so a poll unwinding out of a panic still shows up
+        self.polls += 1;
+        let t = Instant::now();
+        let r = poll();
+        self.last = t.elapsed();
+        self.busy += self.last;
+        r
+    }
+
+    pub(crate) fn idle(&self, elapsed: Duration) -> Duration {
+        elapsed.saturating_sub(self.busy)
+    }
+}
@@ -0,0 +1,388 @@
+use std::{pin::Pin, time::{Duration, Instant}};
+use std::future::Future;
+use std::panic::{self as std_panic, AssertUnwindSafe};
+use std::task::{Context, Poll};
+
+use pin_project_lite::pin_project;
+use tracing::{Level, warn, span::{Span}};
+
+use crate::{ErrorFormat, Levels, Outcome, Status, Panicked};
+use crate::error::{ErrorReport, TaskError};
+use crate::heartbeat::Heartbeat;
+use crate::panic::OnPanic;
+use crate::stats::PollStats;
+use crate::timeout::{Timeout, now};
+#[cfg(feature = "tokio")]
+use crate::timeout::TimedOut;
+
+pub struct TracingTask<F: Future> {
+    span: Span,
+    future: F,
+    is_long_lived: bool,
+    levels: Levels,
+    slow_poll: Option<Duration>,
+    heartbeat: Option<Duration>,
+    on_panic: Option<OnPanic<F::Output>>,
+    timeout: Option<Timeout<F::Output>>,
+    error_report: Option<ErrorReport<F::Output>>,
+    record_ok: Option<RecordOk<F::Output>>
+}
+
+type RecordOk<O> = Box<dyn Fn(&O, &Span) + Send>;
+
+impl<F: Future> TracingTask<F> {
+    pub fn new(span: Span, fut: F) -> TracingTask<F> {
+        TracingTask {
+            span,
+            future: fut,
+            is_long_lived: true,
+            levels: Levels::default(),
+            slow_poll: None,
+            heartbeat: None,
+            on_panic: None,
+            timeout: None,
+            error_report: None,
+            record_ok: None
+        }
+    }
+
+    pub fn new_short_lived(span: Span, fut: F) -> TracingTask<F> {
+        TracingTask {
+            span,
+            future: fut,
+            is_long_lived: false,
+            levels: Levels::default(),
+            slow_poll: None,
+            heartbeat: None,
+            on_panic: None,
+            timeout: None,
+            error_report: None,
+            record_ok: None
+        }
+    }
+
+    pub fn levels(mut self, levels: Levels) -> TracingTask<F> {
+        self.levels = levels;
+        self
+    }
+
+    pub fn start_level(mut self, level: Level) -> TracingTask<F> {
+        self.levels.start = level;
+        self
+    }
+
+    pub fn ok_level(mut self, level: Level) -> TracingTask<F> {
+        self.levels.ok = level;
+        self
+    }
+
+    pub fn err_level(mut self, level: Level) -> TracingTask<F> {
+        self.levels.err = level;
+        self
+    }
+
+    pub fn cancel_level(mut self, level: Level) -> TracingTask<F> {
+        self.levels.cancel = level;
+        self
+    }
+
+    /// Logs a `WARN` event with the poll duration and index whenever a single poll of the wrapped future
+    /// takes longer than `threshold`, i.e. blocks the executor thread, there's no backtrace since the poll has already
+    /// returned by the time it's measured
+    pub fn slow_poll(mut self, threshold: Duration) -> TracingTask<F> {
+        self.slow_poll = Some(threshold);
+        self
+    }
+
+    /// Logs `Still running` at the start level with elapsed time and poll statistics every `interval` until the task
+    /// finishes, with the `tokio` feature enabled and polled on a tokio runtime the task gets woken up for it even when the
+    /// wrapped future is idle, otherwise heartbeats are only checked when it gets polled
+    pub fn heartbeat(mut self, interval: Duration) -> TracingTask<F> {
+        self.heartbeat = Some(interval);
+        self
+    }
+
+    /// Logs `Panicked` with the panic message inside of the task span, then resumes unwinding
+    pub fn log_panics(mut self) -> TracingTask<F> {
+        self.on_panic = Some(OnPanic::Resume);
+        self
+    }
+
+    #[cfg(feature = "tokio")]
+    pub(crate) fn future_mut(&mut self) -> &mut F {
+        &mut self.future
+    }
+
+    pub fn instrument(self) -> InstrumentedTask<F> {
+        InstrumentedTask {
+            future: self.future,
+            on_panic: self.on_panic,
+            timeout: self.timeout,
+            error_report: self.error_report,
+            record_ok: self.record_ok,
+            state: State {
+                span: self.span,
+                is_long_lived: self.is_long_lived,
+                levels: self.levels,
+                slow_poll: self.slow_poll,
+                heartbeat: self.heartbeat.map(Heartbeat::new),
+                started: None,
+                stats: PollStats::default(),
+                polling: false,
+                finished: false
+            }
+        }
+    }
+}
+
+impl<R, E: From<Panicked>, F: Future<Output=Result<R, E>>> TracingTask<F> {
+    /// Logs `Panicked` with the panic message inside of the task span, then resolves to `Err(Panicked.into())`
+    pub fn catch_panics(mut self) -> TracingTask<F> {
+        self.on_panic = Some(OnPanic::Convert(|panicked| Err(panicked.into())));
+        self
+    }
+}
+
+impl<R, E, F: Future<Output=Result<R, E>>> TracingTask<F> {
+    /// Changes how `Finished with` renders the error, see `ErrorFormat`
+    pub fn error_format<M>(mut self, format: ErrorFormat) -> TracingTask<F> where E: TaskError<M> {
+        self.error_report.get_or_insert_with(ErrorReport::new).format = format;
+        self
+    }
+
+    /// Records the error backtrace (when one got captured) in a separate `error.backtrace` field
+    pub fn error_backtrace<M>(mut self) -> TracingTask<F> where E: TaskError<M> {
+        self.error_report.get_or_insert_with(ErrorReport::new).backtrace = true;
+        self
+    }
+
+    /// Calls `hook` with the `Ok` value and the task span before `Finished [OK]...` is logged, so it can `record`
+    /// counts, ids, sizes... into fields declared empty in `span!`
+    pub fn record_ok(mut self, hook: impl Fn(&R, &Span) + Send + 'static) -> TracingTask<F> {
+        self.record_ok = Some(Box::new(move |r, span| {
+            if let Ok(v) = r {
+                hook(v, span);
+            }
+        }));
+        self
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R, E: From<TimedOut>, F: Future<Output=Result<R, E>>> TracingTask<F> {
+    /// Fails the task with `TimedOut` converted into its error type once it runs longer than `timeout`,
+    /// the failure is logged as any other error with `Finished with`
+    pub fn with_timeout(mut self, timeout: Duration) -> TracingTask<F> {
+        self.timeout = Some(Timeout::new(timeout, |timed_out| Err(timed_out.into())));
+        self
+    }
+}
+
+pin_project! {
+    /// Future returned by `TracingTask::instrument`, logs start and outcome of the wrapped future
+    /// inside of the task span without any extra allocations
+    pub struct InstrumentedTask<F>
+    where
+        F: Future
+    {
+        #[pin]
+        future: F,
+        on_panic: Option<OnPanic<F::Output>>,
+        timeout: Option<Timeout<F::Output>>,
+        error_report: Option<ErrorReport<F::Output>>,
+        record_ok: Option<RecordOk<F::Output>>,
+        state: State
+    }
+}
+
+struct State {
+    span: Span,
+    is_long_lived: bool,
+    levels: Levels,
+    slow_poll: Option<Duration>,
+    heartbeat: Option<Heartbeat>,
+    started: Option<Instant>,
+    stats: PollStats,
+    // set while the wrapped future is being polled, still set in drop when its poll unwound out of the task
+    polling: bool,
+    finished: bool
+}
+
+// call site of the task span, only filled in with the `location` feature
+#[derive(Clone, Copy, Default)]
+struct Location {
+    file: Option<&'static str>,
+    line: Option<u32>,
+    module: Option<&'static str>
+}
+
+impl State {
+    fn location(&self) -> Location {
+        #[cfg(feature = "location")]
+        if let Some(meta) = self.span.metadata() {
+            return Location { file: meta.file(), line: meta.line(), module: meta.module_path() };
+        }
+        Location::default()
+    }
+
+    fn check_slow_poll(&self) {
+        if let Some(threshold) = self.slow_poll {
+            if self.stats.last > threshold {
+                warn!(poll_duration = ?self.stats.last, poll_index = self.stats.polls - 1, threshold = ?threshold,
+                    "Slow poll");
+            }
+        }
+    }
+
+    // fills in the fields `span!` declares empty, for backends showing spans rather than events, called after the
+    // outcome event since the opentelemetry layer also derives the span status from `error` fields of events
+    fn record_outcome(&self, outcome: &str, elapsed: Duration, error: Option<&str>) {
+        self.span.record("outcome", outcome);
+        self.span.record("elapsed_ms", elapsed.as_millis() as u64);
+        if let Some(error) = error {
+            self.span.record("error.message", error);
+        }
+        match outcome {
+            "cancelled" => {},
+            "ok" | "none" => { self.span.record("otel.status_code", "OK"); },
+            _ => { self.span.record("otel.status_code", "ERROR"); }
+        }
+        // after the status code, the opentelemetry layer turns the description into an error status carrying it
+        #[cfg(feature = "opentelemetry")]
+        if let Some(error) = error {
+            self.span.record("otel.status_description", error);
+        }
+    }
+}
+
+// a started task dropped before it finished got cancelled - timed out, lost a select! or was aborted, unless it's
+// dropped while unwinding out of its own panicking poll which neither `log_panics` nor `catch_panics` intercepted,
+// a task dropped because something else panicked was still cancelled
+impl Drop for State {
+    fn drop(&mut self) {
+        if let (Some(t), false) = (self.started, self.finished) {
+            let _enter = self.span.enter();
+            let (elapsed, loc) = (now() - t, self.location());
+            if self.polling {
+                event_at!(self.levels.err, elapsed = ?elapsed, busy = ?self.stats.busy, idle = ?self.stats.idle(elapsed),
+                    poll_count = self.stats.polls, file = loc.file, line = loc.line, module = loc.module, "Panicked");
+                self.record_outcome("panicked", elapsed, None);
+            } else {
+                event_at!(self.levels.cancel, elapsed = ?elapsed, busy = ?self.stats.busy,
+                    idle = ?self.stats.idle(elapsed), poll_count = self.stats.polls, file = loc.file, line = loc.line,
+                    module = loc.module, "Cancelled");
+                self.record_outcome("cancelled", elapsed, None);
+            }
+        }
+    }
+}
+
+impl<F: Future> InstrumentedTask<F> where F::Output: Outcome {
+    pub fn boxed_local<'a>(self) -> Pin<Box<dyn Future<Output=F::Output> + 'a>> where F: 'a {
+        Box::pin(self)
+    }
+
+    pub fn boxed<'a>(self) -> Pin<Box<dyn Future<Output=F::Output> + Send + 'a>> where F: Send + 'a {
+        Box::pin(self)
+    }
+}
+
+impl<F: Future> Future for InstrumentedTask<F> where F::Output: Outcome {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let state = this.state;
+        let _enter = state.span.enter();
+
+        let is_long_lived = state.is_long_lived;
+        let levels = state.levels;
+        let t = *state.started.get_or_insert_with(|| {
+            if is_long_lived {
+                event_at!(levels.start, "Starting...");
+            }
+            now()
+        });
+
+        let future = this.future;
+        state.polling = true;
+        let polled = if this.on_panic.is_some() {
+            state.stats.record(|| std_panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))))
+        } else {
+            Ok(state.stats.record(|| future.poll(cx)))
+        };
+        state.polling = false;
+        state.check_slow_poll();
+
+        let r = match polled {
+            Ok(Poll::Ready(r)) => r,
+            Ok(Poll::Pending) => match this.timeout.as_mut().and_then(|timeout| timeout.poll_expired(t, cx)) {
+                Some(r) => r,
+                None => {
+                    if let Some(heartbeat) = &mut state.heartbeat {
+                        if heartbeat.tick(t, cx) {
+                            let (elapsed, stats) = (now() - t, state.stats);
+                            event_at!(levels.start, elapsed = ?elapsed, busy = ?stats.busy, idle = ?stats.idle(elapsed),
+                                poll_count = stats.polls, "Still running");
+                        }
+                    }
+                    return Poll::Pending
+                }
+            },
+            Err(payload) => {
+                state.finished = true;
+                let panicked = Panicked::new(&*payload);
+                let (elapsed, stats, loc) = (now() - t, state.stats, state.location());
+                event_at!(levels.err, panic = panicked.message(), elapsed = ?elapsed, busy = ?stats.busy,
+                    idle = ?stats.idle(elapsed), poll_count = stats.polls, file = loc.file, line = loc.line,
+                    module = loc.module, "Panicked");
+                state.record_outcome("panicked", elapsed, Some(panicked.message()));
+                match this.on_panic {
+                    Some(OnPanic::Convert(convert)) => return Poll::Ready(convert(panicked)),
+                    _ => std_panic::resume_unwind(payload)
+                }
+            }
+        };
+        state.finished = true;
+        let (elapsed, stats) = (now() - t, state.stats);
+        let (busy, idle, poll_count) = (stats.busy, stats.idle(elapsed), stats.polls);
+        let loc = state.location();
+        match r.status() {
+            Status::Err(err) => match this.error_report {
+                None => {
+                    event_at!(levels.err, error = ?err, elapsed = ?elapsed, busy = ?busy, idle = ?idle,
+                        poll_count, file = loc.file, line = loc.line, module = loc.module, "Finished with");
+                    state.record_outcome("error", elapsed, Some(&format!("{:?}", err)))
+                },
+                Some(report) => {
+                    let rendered = report.render(&r, err);
+                    let backtrace = rendered.backtrace.map(tracing::field::display);
+                    match rendered.error {
+                        Some(error) => event_at!(levels.err, error, error.backtrace = backtrace, elapsed = ?elapsed,
+                            busy = ?busy, idle = ?idle, poll_count, file = loc.file, line = loc.line, module = loc.module,
+                            "Finished with"),
+                        None => event_at!(levels.err, error = %rendered.message,
+                            error.chain = rendered.chain.as_ref().map(tracing::field::debug), error.backtrace = backtrace,
+                            elapsed = ?elapsed, busy = ?busy, idle = ?idle, poll_count, file = loc.file, line = loc.line,
+                            module = loc.module, "Finished with")
+                    }
+                    state.record_outcome("error", elapsed, Some(&rendered.summary))
+                }
+            },
+            Status::None => {
+                event_at!(levels.ok, elapsed = ?elapsed, busy = ?busy, idle = ?idle, poll_count, file = loc.file,
+                    line = loc.line, module = loc.module, "Finished [NONE]...");
+                state.record_outcome("none", elapsed, None)
+            },
+            Status::Ok => {
+                if let Some(record_ok) = this.record_ok {
+                    record_ok(&r, &state.span);
+                }
+                event_at!(levels.ok, elapsed = ?elapsed, busy = ?busy, idle = ?idle, poll_count, file = loc.file,
+                    line = loc.line, module = loc.module, "Finished [OK]...");
+                state.record_outcome("ok", elapsed, None)
+            }
+        }
+        Poll::Ready(r)
+    }
+}
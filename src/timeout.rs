@@ -0,0 +1,87 @@
+#[cfg(feature = "tokio")]
+pub use self::imp::TimedOut;
+pub(crate) use self::imp::{Timeout, now};
+
+#[cfg(feature = "tokio")]
+mod imp {
+    use std::error::Error;
+    use std::fmt::{self, Display};
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use std::time::{Duration, Instant};
+
+    use tokio::time::{sleep_until, Sleep};
+
+    // tokio's clock, so timers and the time they're compared against agree when a test pauses time
+    pub(crate) fn now() -> Instant {
+        tokio::time::Instant::now().into_std()
+    }
+
+    /// Error a task set up with `TracingTask::with_timeout` fails with once it runs out of time
+    #[derive(Debug)]
+    pub struct TimedOut {
+        timeout: Duration,
+        elapsed: Duration
+    }
+
+    impl TimedOut {
+        pub fn timeout(&self) -> Duration {
+            self.timeout
+        }
+
+        pub fn elapsed(&self) -> Duration {
+            self.elapsed
+        }
+    }
+
+    impl Display for TimedOut {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "task timed out after {:?} (deadline {:?})", self.elapsed, self.timeout)
+        }
+    }
+
+    impl Error for TimedOut {}
+
+    pub(crate) struct Timeout<O> {
+        timeout: Duration,
+        convert: fn(TimedOut) -> O,
+        sleep: Option<Pin<Box<Sleep>>>
+    }
+
+    impl<O> Timeout<O> {
+        pub(crate) fn new(timeout: Duration, convert: fn(TimedOut) -> O) -> Timeout<O> {
+            Timeout { timeout, convert, sleep: None }
+        }
+
+        pub(crate) fn poll_expired(&mut self, started: Instant, cx: &mut Context<'_>) -> Option<O> {
+            let deadline = started + self.timeout;
+            let sleep = self.sleep.get_or_insert_with(|| Box::pin(sleep_until(deadline.into())));
+            match sleep.as_mut().poll(cx) {
+                Poll::Ready(()) => Some((self.convert)(TimedOut { timeout: self.timeout, elapsed: now() - started })),
+                Poll::Pending => None
+            }
+        }
+    }
+}
+
+// stands in for the tokio backed timeout, so InstrumentedTask keeps the same shape without the feature
+#[cfg(not(feature = "tokio"))]
+mod imp {
+    use std::convert::Infallible;
+    use std::marker::PhantomData;
+    use std::task::Context;
+    use std::time::Instant;
+
+    pub(crate) fn now() -> Instant {
+        Instant::now()
+    }
+
+    pub(crate) struct Timeout<O>(Infallible, PhantomData<fn() -> O>);
+
+    impl<O> Timeout<O> {
+        pub(crate) fn poll_expired(&mut self, _started: Instant, _cx: &mut Context<'_>) -> Option<O> {
+            match self.0 {}
+        }
+    }
+}
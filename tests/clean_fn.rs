@@ -0,0 +1,80 @@
+use tracing_tools::{clean_fn, clean_fn_with, NameStyle};
+
+// `function!()` outputs as produced by rustc in the given context
+const CASES: &[(&str, &str)] = &[
+    // fn
+    ("app::plain", "app::plain"),
+    ("app::api::handlers::fetch", "handlers::fetch"),
+    // methods
+    ("app::S::method", "S::method"),
+    ("app::G<_>::m", "G::m"),
+    ("app::G<alloc::string::String>::m", "G::m"),
+    ("app::G<core::option::Option<u8>>::m", "G::m"),
+    // trait impls
+    ("<app::S as app::Tr>::run", "S::run"),
+    ("<app::G<_> as app::Tr>::gen", "G::gen"),
+    ("<app::G<alloc::vec::Vec<u8>> as core::ops::Drop>::drop", "G::drop"),
+    ("<app::S as app::Tr<u8>>::run::{{closure}}", "S::run"),
+    ("<&str as app::Tr>::run", "&str::run"),
+    // closures and async
+    ("app::closure::{{closure}}", "app::closure"),
+    ("app::afn::{{closure}}", "app::afn"),
+    ("app::afn::{{closure}}::{{closure}}", "app::afn"),
+    ("app::nested::{{closure}}::{{closure}}::{{closure}}", "app::nested"),
+    ("app::main::{{closure}}", "app::main"),
+    ("app::S::method::{{closure}}", "S::method"),
+    ("app::ablock::{{async_block}}", "app::ablock"),
+    ("app::afn::{{async_fn_body}}", "app::afn"),
+    ("app::afn::{{async_fn_body}}::{{async_block}}::{{closure}}", "app::afn"),
+];
+
+#[test]
+fn clean_fn_table() {
+    for (input, expected) in CASES {
+        assert_eq!(clean_fn(input), *expected, "clean_fn({:?})", input);
+    }
+}
+
+const STYLED: &[(&str, NameStyle, &str)] = &[
+    ("app::db::load::{{closure}}", NameStyle::Full, "app::db::load"),
+    ("app::db::load::{{closure}}", NameStyle::Relative, "db::load"),
+    ("app::db::load::{{closure}}", NameStyle::Function, "load"),
+    ("app::db::load::{{closure}}", NameStyle::Method, "load"),
+    ("app::db::Repo<_>::get", NameStyle::Full, "app::db::Repo::get"),
+    ("app::db::Repo<_>::get", NameStyle::Relative, "db::Repo::get"),
+    ("app::db::Repo<_>::get", NameStyle::Function, "get"),
+    ("app::db::Repo<_>::get", NameStyle::Method, "Repo::get"),
+    ("<app::db::Repo as app::Store>::get", NameStyle::Full, "app::db::Repo::get"),
+    ("<app::db::Repo as app::Store>::get", NameStyle::Method, "Repo::get"),
+    ("<&str as app::Store>::get", NameStyle::Method, "&str::get"),
+    ("main", NameStyle::Relative, "main"),
+    ("app::main", NameStyle::Relative, "main"),
+];
+
+#[test]
+fn clean_fn_with_styles() {
+    for (input, style, expected) in STYLED {
+        assert_eq!(clean_fn_with(*style, input), *expected, "clean_fn_with({:?}, {:?})", style, input);
+    }
+}
+
+#[test]
+fn clean_fn_of_real_function_names() {
+    struct S;
+
+    impl S {
+        fn method(&self) -> &'static str {
+            tracing_tools::function!()
+        }
+    }
+
+    async fn afn() -> &'static str {
+        tracing_tools::function!()
+    }
+
+    let closure = || tracing_tools::function!();
+    assert_eq!(clean_fn(S.method()), "S::method");
+    assert_eq!(clean_fn(closure()), "clean_fn::clean_fn_of_real_function_names");
+    let name = tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(afn());
+    assert_eq!(clean_fn(name), "clean_fn_of_real_function_names::afn");
+}
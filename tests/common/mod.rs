@@ -0,0 +1,80 @@
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use tracing::{Event, Subscriber, field::{Field, Visit}, span::{Attributes, Id, Record}, subscriber::DefaultGuard};
+use tracing_subscriber::{layer::{Context, Layer}, prelude::*};
+
+pub type Fields = BTreeMap<String, String>;
+
+/// Keeps every event (with its `level`) and the latest value of every span field seen on the current thread
+#[derive(Clone, Default)]
+pub struct Recorder {
+    events: Arc<Mutex<Vec<Fields>>>,
+    spans: Arc<Mutex<Fields>>
+}
+
+impl Recorder {
+    pub fn install() -> (Recorder, DefaultGuard) {
+        let recorder = Recorder::default();
+        let guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+        (recorder, guard)
+    }
+
+    pub fn events(&self, message: &str) -> Vec<Fields> {
+        self.events.lock().unwrap().iter().filter(|event| event["message"] == message).cloned().collect()
+    }
+
+    pub fn event(&self, message: &str) -> Fields {
+        let mut events = self.events(message);
+        assert_eq!(events.len(), 1, "expected a single {:?} event, got {:?}", message, self.events.lock().unwrap());
+        events.remove(0)
+    }
+
+    pub fn span_field(&self, name: &str) -> Option<String> {
+        self.spans.lock().unwrap().get(name).cloned()
+    }
+}
+
+struct Collect<'a>(&'a mut Fields);
+
+impl Visit for Collect<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.insert(field.name().to_string(), format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    // walks the sources, so tests can tell errors recorded as `&dyn Error` from their Display rendering
+    fn record_error(&mut self, field: &Field, value: &(dyn Error + 'static)) {
+        let mut chain = vec![value.to_string()];
+        let mut source = value.source();
+        while let Some(error) = source {
+            chain.push(error.to_string());
+            source = error.source();
+        }
+        self.0.insert(field.name().to_string(), chain.join(" <- "));
+    }
+}
+
+impl<S: Subscriber> Layer<S> for Recorder {
+    fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+        attrs.record(&mut Collect(&mut self.spans.lock().unwrap()));
+    }
+
+    fn on_record(&self, _id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        values.record(&mut Collect(&mut self.spans.lock().unwrap()));
+    }
+
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut fields = Fields::new();
+        fields.insert("level".to_string(), event.metadata().level().to_string());
+        event.record(&mut Collect(&mut fields));
+        self.events.lock().unwrap().push(fields);
+    }
+}
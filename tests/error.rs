@@ -0,0 +1,83 @@
+#![cfg(feature = "anyhow")]
+
+use std::sync::Once;
+
+use anyhow::{anyhow, Context};
+use tracing_tools::{span, ErrorFormat, TracingTask};
+
+mod common;
+use common::Recorder;
+
+// backtrace capture is decided once per process, so every test here turns it on before creating an error
+fn capture_backtraces() {
+    static ONCE: Once = Once::new();
+    ONCE.call_once(|| std::env::set_var("RUST_LIB_BACKTRACE", "1"));
+}
+
+fn fetch() -> anyhow::Result<()> {
+    Err(anyhow!("connection refused")).context("fetch failed")
+}
+
+#[tokio::test]
+async fn span_records_debug_without_an_error_format() {
+    capture_backtraces();
+    let (recorder, _guard) = Recorder::install();
+    let err = TracingTask::new(span!(), async { fetch() }).instrument().await.unwrap_err();
+
+    assert_eq!(recorder.span_field("error.message"), Some(format!("{:?}", err)));
+    assert!(format!("{:?}", err).contains("Stack backtrace"));
+}
+
+#[tokio::test]
+async fn span_records_the_error_chain_whatever_the_error_format() {
+    capture_backtraces();
+    let (recorder, _guard) = Recorder::install();
+    let task = TracingTask::new(span!(), async { fetch() }).error_format(ErrorFormat::Debug).instrument();
+    assert!(task.await.is_err());
+
+    assert!(recorder.event("Finished with")["error"].contains("Stack backtrace"));
+    assert_eq!(recorder.span_field("error.message").as_deref(), Some("fetch failed: connection refused"));
+}
+
+#[tokio::test]
+async fn borrowed_errors_are_recorded_with_debug() {
+    let (recorder, _guard) = Recorder::install();
+    let reason = String::from("out of coffee");
+    let task = TracingTask::new(span!(), async { Err::<(), _>(reason.as_str()) }).instrument();
+    assert!(task.await.is_err());
+
+    assert_eq!(recorder.span_field("error.message").as_deref(), Some("\"out of coffee\""));
+}
+
+#[tokio::test]
+async fn structured_errors_reach_record_error() {
+    let (recorder, _guard) = Recorder::install();
+    let task = TracingTask::new(span!(), async { fetch() }).error_format(ErrorFormat::Structured).instrument();
+    assert!(task.await.is_err());
+
+    assert_eq!(recorder.event("Finished with")["error"], "fetch failed <- connection refused");
+}
+
+#[tokio::test]
+async fn error_formats_fill_error_fields() {
+    capture_backtraces();
+    let cases = [
+        (ErrorFormat::Display, false, "fetch failed", None),
+        (ErrorFormat::Alternate, false, "fetch failed: connection refused", None),
+        (ErrorFormat::Chain, false, "fetch failed", Some(r#"["fetch failed", "connection refused"]"#)),
+        (ErrorFormat::Display, true, "fetch failed", None),
+        (ErrorFormat::Chain, true, "fetch failed", Some(r#"["fetch failed", "connection refused"]"#))
+    ];
+    for (format, backtrace, error, chain) in cases {
+        let (recorder, _guard) = Recorder::install();
+        let task = TracingTask::new(span!(), async { fetch() }).error_format(format);
+        let task = if backtrace { task.error_backtrace() } else { task };
+        assert!(task.instrument().await.is_err());
+
+        let event = recorder.event("Finished with");
+        let case = (format, backtrace);
+        assert_eq!(event["error"], error, "{:?}", case);
+        assert_eq!(event.get("error.chain").map(String::as_str), chain, "{:?}", case);
+        assert_eq!(event.get("error.backtrace").map(|bt| bt.contains("fetch")), backtrace.then_some(true), "{:?}", case);
+    }
+}
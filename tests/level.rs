@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use tracing::Level;
+use tracing_tools::{span, set_default_levels, Levels, TracingTask};
+
+mod common;
+use common::Recorder;
+
+async fn fail() -> anyhow::Result<()> {
+    Err(anyhow::anyhow!("out of coffee"))
+}
+
+async fn cancel(task: impl std::future::Future) {
+    assert!(tokio::time::timeout(Duration::from_millis(1), task).await.is_err());
+}
+
+async fn stuck() -> anyhow::Result<()> {
+    tokio::time::sleep(Duration::from_secs(10)).await;
+    Ok(())
+}
+
+fn levels(recorder: &Recorder) -> [String; 4] {
+    ["Starting...", "Finished [OK]...", "Finished with", "Cancelled"].map(|message| recorder.event(message)["level"].clone())
+}
+
+// a single test, default levels are global
+#[tokio::test]
+async fn events_are_logged_at_task_or_default_levels() {
+    let (recorder, _guard) = Recorder::install();
+    TracingTask::new(span!(), async { Ok::<_, anyhow::Error>(()) }).instrument().await.unwrap();
+    TracingTask::new_short_lived(span!(), fail()).instrument().await.unwrap_err();
+    cancel(TracingTask::new_short_lived(span!(), stuck()).instrument()).await;
+    assert_eq!(levels(&recorder), ["INFO", "INFO", "ERROR", "WARN"]);
+
+    let (recorder, _guard) = Recorder::install();
+    TracingTask::new(span!(), async { Ok::<_, anyhow::Error>(()) })
+        .start_level(Level::DEBUG).ok_level(Level::TRACE).instrument().await.unwrap();
+    TracingTask::new_short_lived(span!(), fail()).err_level(Level::WARN).instrument().await.unwrap_err();
+    cancel(TracingTask::new_short_lived(span!(), stuck()).cancel_level(Level::INFO).instrument()).await;
+    assert_eq!(levels(&recorder), ["DEBUG", "TRACE", "WARN", "INFO"]);
+
+    let (recorder, _guard) = Recorder::install();
+    let custom = Levels::new(Level::TRACE, Level::DEBUG, Level::INFO).with_cancel(Level::ERROR);
+    TracingTask::new(span!(), async { Ok::<_, anyhow::Error>(()) }).levels(custom).instrument().await.unwrap();
+    TracingTask::new_short_lived(span!(), fail()).levels(custom).instrument().await.unwrap_err();
+    cancel(TracingTask::new_short_lived(span!(), stuck()).levels(custom).instrument()).await;
+    assert_eq!(levels(&recorder), ["TRACE", "DEBUG", "INFO", "ERROR"]);
+
+    let (recorder, _guard) = Recorder::install();
+    set_default_levels(Levels::new(Level::DEBUG, Level::DEBUG, Level::WARN).with_cancel(Level::TRACE));
+    TracingTask::new(span!(), async { Ok::<_, anyhow::Error>(()) }).instrument().await.unwrap();
+    TracingTask::new_short_lived(span!(), fail()).instrument().await.unwrap_err();
+    cancel(TracingTask::new_short_lived(span!(), stuck()).instrument()).await;
+    assert_eq!(levels(&recorder), ["DEBUG", "DEBUG", "WARN", "TRACE"]);
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "location")]
+
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Duration;
+
+use tracing::Span;
+use tracing_tools::{span, TracingTask};
+
+mod common;
+use common::{Fields, Recorder};
+
+fn assert_location(fields: &Fields, line: u32) {
+    assert_eq!(fields["file"], "tests/location.rs", "{:?}", fields);
+    assert_eq!(fields["line"], line.to_string(), "{:?}", fields);
+    assert_eq!(fields["module"], "location", "{:?}", fields);
+}
+
+fn fail(span: Span, fail: bool) -> TracingTask<impl std::future::Future<Output=anyhow::Result<()>>> {
+    TracingTask::new_short_lived(span, async move {
+        if fail {
+            anyhow::bail!("out of coffee");
+        }
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn span_and_finish_events_carry_the_call_site() {
+    let (recorder, _guard) = Recorder::install();
+    let (span, line) = (span!(), line!());
+    fail(span, false).instrument().await.unwrap();
+    assert_location(&recorder.event("Finished [OK]..."), line);
+    assert_eq!(recorder.span_field("file").as_deref(), Some("tests/location.rs"));
+    assert_eq!(recorder.span_field("line"), Some(line.to_string()));
+    assert_eq!(recorder.span_field("module").as_deref(), Some("location"));
+
+    let (span, line) = (span!(), line!());
+    fail(span, true).instrument().await.unwrap_err();
+    assert_location(&recorder.event("Finished with"), line);
+}
+
+#[tokio::test]
+async fn cancelled_event_carries_the_call_site() {
+    let (recorder, _guard) = Recorder::install();
+    let (span, line) = (span!(), line!());
+    let task = TracingTask::new_short_lived(span, async {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        Ok::<_, anyhow::Error>(())
+    }).instrument();
+    assert!(tokio::time::timeout(Duration::from_millis(1), task).await.is_err());
+
+    assert_location(&recorder.event("Cancelled"), line);
+}
+
+#[test]
+fn panicked_events_carry_the_call_site() {
+    let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let run = |log_panics: bool| {
+        let (recorder, _guard) = Recorder::install();
+        let (span, line) = (span!(), line!());
+        let task = TracingTask::new_short_lived(span, async {
+            panic!("boom");
+            #[allow(unreachable_code)]
+            Ok::<_, anyhow::Error>(())
+        });
+        let task = if log_panics { task.log_panics() } else { task };
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| rt.block_on(task.instrument()))).is_err());
+        assert_location(&recorder.event("Panicked"), line);
+    };
+    // logged by log_panics and by the drop of a task unwinding without it
+    run(true);
+    run(false);
+}
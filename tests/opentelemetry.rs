@@ -0,0 +1,43 @@
+#![cfg(all(feature = "opentelemetry", feature = "anyhow"))]
+
+use std::future::Future;
+
+use anyhow::anyhow;
+use opentelemetry::trace::{SpanKind, Status, TracerProvider};
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+use tracing_subscriber::prelude::*;
+use tracing_tools::{span, ErrorFormat, TracingTask};
+
+fn run_task(fail: bool) -> impl Future<Output=anyhow::Result<usize>> {
+    TracingTask::new_short_lived(span!(), async move {
+        if fail {
+            return Err(anyhow!("out of coffee"));
+        }
+        Ok(1)
+    }).error_format(ErrorFormat::Display).instrument()
+}
+
+// a single test, scoped subscribers of parallel tests racing on the shared callsite interest make it flaky
+#[test]
+fn task_spans_are_exported_with_otel_fields() {
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder().with_simple_exporter(exporter.clone()).build();
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("tracing-tools")));
+
+    tracing::subscriber::with_default(subscriber, || {
+        let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        assert_eq!(rt.block_on(run_task(false)).unwrap(), 1);
+        assert!(rt.block_on(run_task(true)).is_err());
+    });
+
+    let spans = exporter.get_finished_spans().unwrap();
+    assert_eq!(spans.len(), 2);
+    for span in &spans {
+        assert_eq!(span.name, "opentelemetry::run_task");
+        assert_eq!(span.span_kind, SpanKind::Internal);
+    }
+    assert_eq!(spans[0].status, Status::Ok);
+    assert_eq!(spans[1].status, Status::error("out of coffee"));
+    assert!(spans[1].attributes.iter().any(|kv| kv.key.as_str() == "outcome" && kv.value.as_str() == "error"));
+}
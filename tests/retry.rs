@@ -0,0 +1,68 @@
+#![cfg(feature = "tokio")]
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use tracing_tools::{span, Backoff, ExponentialBackoff, FixedBackoff, Jitter, TracingTask};
+
+mod common;
+use common::Recorder;
+
+const MS: Duration = Duration::from_millis(1);
+
+#[test]
+fn fixed_backoff_gives_up_after_attempts() {
+    let mut backoff = FixedBackoff::new(MS * 10, 3);
+    assert_eq!(backoff.next_delay(1), Some(MS * 10));
+    assert_eq!(backoff.next_delay(2), Some(MS * 10));
+    assert_eq!(backoff.next_delay(3), None);
+}
+
+#[test]
+fn exponential_backoff_grows_up_to_max_delay() {
+    let mut backoff = ExponentialBackoff::new(MS * 10, 6).max_delay(MS * 50);
+    let delays = (1..=6).map(|attempt| backoff.next_delay(attempt)).collect::<Vec<_>>();
+    assert_eq!(delays, [Some(MS * 10), Some(MS * 20), Some(MS * 40), Some(MS * 50), Some(MS * 50), None]);
+
+    let mut backoff = ExponentialBackoff::new(MS, 4).factor(3);
+    assert_eq!(backoff.next_delay(3), Some(MS * 9));
+    assert_eq!(backoff.next_delay(0), Some(MS));
+}
+
+#[test]
+fn exponential_backoff_caps_overflowing_delays() {
+    let mut backoff = ExponentialBackoff::new(Duration::from_secs(1), u32::MAX).max_delay(Duration::from_secs(60));
+    assert_eq!(backoff.next_delay(100), Some(Duration::from_secs(60)));
+}
+
+#[test]
+fn jitter_stays_between_half_and_full_delay() {
+    let mut backoff = Jitter::new(FixedBackoff::new(MS * 100, 1000));
+    for attempt in 1..1000 {
+        let delay = backoff.next_delay(attempt).unwrap();
+        assert!(delay >= MS * 50 && delay <= MS * 100, "{:?}", delay);
+    }
+    assert_eq!(backoff.next_delay(1000), None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn retry_logs_failed_attempts_until_one_succeeds() {
+    let (recorder, _guard) = Recorder::install();
+    let calls = AtomicU32::new(0);
+    let n = TracingTask::retry(span!(), FixedBackoff::new(Duration::from_secs(1), 5), || async {
+        match calls.fetch_add(1, Ordering::Relaxed) + 1 {
+            n if n < 3 => Err(anyhow!("connection refused")).context("fetch failed"),
+            n => Ok(n)
+        }
+    }).instrument().await.unwrap();
+
+    assert_eq!(n, 3);
+    let failed = recorder.events("Attempt failed");
+    assert_eq!(failed.len(), 2);
+    for event in failed {
+        assert_eq!(event["error"], "fetch failed: connection refused");
+        assert_eq!(event["retry_in"], "1s");
+    }
+    assert_eq!(recorder.span_field("outcome").as_deref(), Some("ok"));
+}
@@ -0,0 +1,17 @@
+#[test]
+fn span_macro_forms() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/span/*.rs");
+}
+
+mod common;
+
+#[test]
+fn span_names_differ_per_call_site() {
+    let (_recorder, _guard) = common::Recorder::install();
+    let first = tracing_tools::span!();
+    let second = tracing_tools::span!();
+    let (first, second) = (first.metadata().unwrap().name(), second.metadata().unwrap().name());
+    assert_eq!(first, format!("{}:{}", module_path!(), line!() - 3));
+    assert_eq!(second, format!("{}:{}", module_path!(), line!() - 3));
+}
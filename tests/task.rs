@@ -0,0 +1,175 @@
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::task::{Context, Wake, Waker};
+use std::thread;
+use std::time::Duration;
+
+use tracing_tools::{span, TracingTask};
+
+mod common;
+use common::Recorder;
+
+fn assert_send<T: Send>(_: &T) {}
+
+#[test]
+fn instrumented_task_is_send_when_the_future_is() {
+    let task = TracingTask::new(span!(), async {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        Ok::<_, anyhow::Error>(1)
+    }).slow_poll(Duration::from_millis(10)).heartbeat(Duration::from_secs(1)).record_ok(|n, span| { span.record("n", *n); }).instrument();
+    assert_send(&task);
+    assert_send(&task.boxed());
+}
+
+#[tokio::test]
+async fn dropped_task_logs_cancelled() {
+    let (recorder, _guard) = Recorder::install();
+    let task = TracingTask::new(span!(), async {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        Ok::<_, anyhow::Error>(())
+    }).instrument();
+    assert!(tokio::time::timeout(Duration::from_millis(5), task).await.is_err());
+
+    assert_ne!(recorder.event("Cancelled")["poll_count"], "0");
+    assert!(recorder.events("Finished with").is_empty());
+    assert_eq!(recorder.span_field("outcome").as_deref(), Some("cancelled"));
+}
+
+#[test]
+fn task_unwinding_out_of_a_panic_is_not_cancelled() {
+    let (recorder, _guard) = Recorder::install();
+    let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let task = TracingTask::new_short_lived(span!(), async {
+        panic!("boom");
+        #[allow(unreachable_code)]
+        Ok::<_, anyhow::Error>(())
+    }).instrument();
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| rt.block_on(task))).is_err());
+
+    assert_eq!(recorder.event("Panicked")["poll_count"], "1");
+    assert!(recorder.events("Cancelled").is_empty());
+    assert_eq!(recorder.span_field("outcome").as_deref(), Some("panicked"));
+    assert_eq!(recorder.span_field("otel.status_code").as_deref(), Some("ERROR"));
+}
+
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+#[test]
+fn heartbeat_outside_of_a_tokio_runtime_is_checked_on_polls() {
+    let (recorder, _guard) = Recorder::install();
+    let mut task = Box::pin(TracingTask::new(span!(), std::future::pending::<anyhow::Result<()>>())
+        .heartbeat(Duration::from_millis(1)).instrument());
+    let waker = Waker::from(Arc::new(NoopWaker));
+    let mut cx = Context::from_waker(&waker);
+    for _ in 0..3 {
+        assert!(task.as_mut().poll(&mut cx).is_pending());
+        thread::sleep(Duration::from_millis(2));
+    }
+
+    assert_eq!(recorder.events("Still running").len(), 2);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test(start_paused = true)]
+async fn heartbeat_wakes_up_an_idle_task() {
+    let (recorder, _guard) = Recorder::install();
+    let task = TracingTask::new(span!(), async {
+        tokio::time::sleep(Duration::from_secs(35)).await;
+        Ok::<_, anyhow::Error>(())
+    }).heartbeat(Duration::from_secs(10)).instrument();
+    task.await.unwrap();
+
+    assert_eq!(recorder.events("Still running").len(), 3);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test(start_paused = true)]
+async fn timed_out_task_finishes_with_timed_out() {
+    let (recorder, _guard) = Recorder::install();
+    let err = TracingTask::new(span!(), async {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        Ok::<_, anyhow::Error>(())
+    }).with_timeout(Duration::from_secs(5)).instrument().await.unwrap_err();
+
+    let timed_out = err.downcast_ref::<tracing_tools::TimedOut>().unwrap();
+    assert_eq!(timed_out.timeout(), Duration::from_secs(5));
+    assert_eq!(timed_out.elapsed(), Duration::from_secs(5));
+    let event = recorder.event("Finished with");
+    assert!(event["error"].starts_with("task timed out after 5s (deadline 5s)"));
+    assert_eq!(event["elapsed"], "5s");
+    assert!(recorder.events("Cancelled").is_empty());
+    assert_eq!(recorder.span_field("outcome").as_deref(), Some("error"));
+}
+
+#[test]
+fn log_panics_logs_panicked_and_resumes_unwinding() {
+    let (recorder, _guard) = Recorder::install();
+    let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let task = TracingTask::new_short_lived(span!(), async {
+        panic!("boom");
+        #[allow(unreachable_code)]
+        Ok::<_, anyhow::Error>(())
+    }).log_panics().instrument();
+    let payload = panic::catch_unwind(AssertUnwindSafe(|| rt.block_on(task))).unwrap_err();
+
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+    let event = recorder.event("Panicked");
+    assert_eq!((event["level"].as_str(), event["panic"].as_str()), ("ERROR", "boom"));
+    assert!(recorder.events("Cancelled").is_empty());
+}
+
+#[test]
+fn task_dropped_by_a_sibling_panic_is_cancelled() {
+    let (recorder, _guard) = Recorder::install();
+    let rt = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+    let healthy = TracingTask::new_short_lived(span!(), async {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        Ok::<_, anyhow::Error>(())
+    }).instrument();
+    let other = async {
+        panic!("boom");
+        #[allow(unreachable_code)]
+        Ok::<_, anyhow::Error>(())
+    };
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| rt.block_on(async { tokio::join!(healthy, other) }))).is_err());
+
+    assert_eq!(recorder.event("Cancelled")["poll_count"], "1");
+    assert!(recorder.events("Panicked").is_empty());
+    assert_eq!(recorder.span_field("outcome").as_deref(), Some("cancelled"));
+}
+
+#[tokio::test]
+async fn slow_poll_reports_blocking_polls() {
+    let (recorder, _guard) = Recorder::install();
+    TracingTask::new_short_lived(span!(), async {
+        tokio::task::yield_now().await;
+        thread::sleep(Duration::from_millis(20));
+        tokio::task::yield_now().await;
+        Ok::<_, anyhow::Error>(())
+    }).slow_poll(Duration::from_millis(10)).instrument().await.unwrap();
+
+    let event = recorder.event("Slow poll");
+    assert_eq!((event["level"].as_str(), event["poll_index"].as_str(), event["threshold"].as_str()), ("WARN", "1", "10ms"));
+    assert!(event.contains_key("poll_duration"));
+}
+
+#[tokio::test]
+async fn record_ok_records_declared_fields() {
+    let (recorder, _guard) = Recorder::install();
+    let rows = TracingTask::new_short_lived(span!(rows = tracing::field::Empty, last_id = tracing::field::Empty), async {
+        Ok::<_, anyhow::Error>(vec![3, 42])
+    }).record_ok(|rows, span| {
+        span.record("rows", rows.len());
+        span.record("last_id", rows.last().copied());
+    }).instrument().await.unwrap();
+
+    assert_eq!(rows, [3, 42]);
+    assert_eq!(recorder.span_field("rows").as_deref(), Some("2"));
+    assert_eq!(recorder.span_field("last_id").as_deref(), Some("42"));
+    assert!(!recorder.event("Finished [OK]...").contains_key("result"));
+}
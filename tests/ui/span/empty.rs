@@ -0,0 +1,11 @@
+use tracing::Level;
+use tracing_tools::span;
+
+fn main() {
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry());
+
+    let span = span!();
+    let meta = span.metadata().unwrap();
+    assert_eq!(*meta.level(), Level::ERROR);
+    assert!(meta.fields().field("name").is_some());
+}
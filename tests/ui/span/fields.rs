@@ -0,0 +1,15 @@
+use tracing::Level;
+use tracing_tools::span;
+
+fn main() {
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry());
+
+    let n = 1;
+    let s = "str";
+    let span = span!(n = n, another_field = "bow wow!", ?s, %s,);
+    let meta = span.metadata().unwrap();
+    assert_eq!(*meta.level(), Level::ERROR);
+    for field in &["name", "n", "another_field", "s"] {
+        assert!(meta.fields().field(field).is_some(), "missing {}", field);
+    }
+}
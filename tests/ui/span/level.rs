@@ -0,0 +1,12 @@
+use tracing::Level;
+use tracing_tools::span;
+
+fn main() {
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry());
+
+    let span = span!(Level::INFO);
+    assert_eq!(*span.metadata().unwrap().level(), Level::INFO);
+
+    let span = span!(tracing::Level::TRACE);
+    assert_eq!(*span.metadata().unwrap().level(), Level::TRACE);
+}
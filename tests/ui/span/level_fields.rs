@@ -0,0 +1,14 @@
+use tracing::Level;
+use tracing_tools::span;
+
+fn main() {
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry());
+
+    let n = 1;
+    let span = span!(Level::INFO, n = n, another_field = "bow wow!");
+    let meta = span.metadata().unwrap();
+    assert_eq!(*meta.level(), Level::INFO);
+    assert!(meta.fields().field("n").is_some());
+    assert!(meta.fields().field("another_field").is_some());
+    assert!(meta.fields().field("Level::INFO").is_none());
+}
@@ -0,0 +1,43 @@
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use tracing::{Level, field::{Field, Visit}, span::{Attributes, Id}, Subscriber};
+use tracing_subscriber::{layer::{Context, Layer}, prelude::*};
+use tracing_tools::span;
+
+#[derive(Clone, Default)]
+struct Names(Arc<Mutex<Vec<String>>>);
+
+impl Visit for Names {
+    fn record_debug(&mut self, _field: &Field, _value: &dyn Debug) {}
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "name" {
+            self.0.lock().unwrap().push(value.to_string());
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for Names {
+    fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+        attrs.record(&mut self.clone());
+    }
+}
+
+fn main() {
+    let names = Names::default();
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(names.clone()));
+
+    let n = 1;
+    let span = span!("fetch", n = n);
+    assert_eq!(*span.metadata().unwrap().level(), Level::ERROR);
+    let span = span!(Level::DEBUG, "fetch_more");
+    assert_eq!(*span.metadata().unwrap().level(), Level::DEBUG);
+    let span = span!(Level::WARN, "fetch_even_more", n = n);
+    assert_eq!(*span.metadata().unwrap().level(), Level::WARN);
+    let _span = span!(Level::WARN, n = n);
+
+    let names = names.0.lock().unwrap();
+    assert_eq!(names[..3], ["fetch", "fetch_more", "fetch_even_more"]);
+    assert!(names[3].ends_with("::main"), "{}", names[3]);
+}
@@ -0,0 +1,21 @@
+use tracing::Level;
+use tracing_tools::{span, info_span, NameStyle};
+
+fn main() {
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry());
+
+    let n = 1;
+    let spans = vec![
+        (span!(name_style: NameStyle::Full), Level::ERROR),
+        (span!(name_style: NameStyle::Function, n = n), Level::ERROR),
+        (span!(name_style: NameStyle::Relative, Level::INFO, n = n), Level::INFO),
+        (span!(name_style: NameStyle::Method, tracing::Level::DEBUG, "fetch"), Level::DEBUG),
+        (info_span!(name_style: NameStyle::Short, n = n), Level::INFO),
+    ];
+    for (span, level) in spans {
+        let meta = span.metadata().unwrap();
+        assert_eq!(*meta.level(), level);
+        assert!(meta.fields().field("name").is_some());
+        assert!(meta.fields().field("name_style").is_none());
+    }
+}
@@ -0,0 +1,20 @@
+use tracing::Level;
+use tracing_tools::{error_span, warn_span, info_span, debug_span, trace_span};
+
+fn main() {
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry());
+
+    let n = 1;
+    let spans = vec![
+        (error_span!(), Level::ERROR),
+        (warn_span!(n = n), Level::WARN),
+        (info_span!("fetch"), Level::INFO),
+        (debug_span!("fetch", n = n, ?n), Level::DEBUG),
+        (trace_span!(n = n), Level::TRACE),
+    ];
+    for (span, level) in spans {
+        let meta = span.metadata().unwrap();
+        assert_eq!(*meta.level(), level);
+        assert!(meta.fields().field("name").is_some());
+    }
+}
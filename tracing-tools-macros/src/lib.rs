@@ -0,0 +1,154 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned, ToTokens};
+use syn::{parse_macro_input, spanned::Spanned, ItemFn, LitStr, ReturnType, Type};
+
+#[derive(Default)]
+struct Args {
+    fields: Option<TokenStream2>,
+    short_lived: bool,
+    log_panics: bool,
+    catch_panics: bool,
+    name: Option<LitStr>,
+    level: Option<TokenStream2>,
+    start_level: Option<TokenStream2>,
+    ok_level: Option<TokenStream2>,
+    err_level: Option<TokenStream2>,
+    cancel_level: Option<TokenStream2>
+}
+
+fn parse_level(lit: LitStr) -> syn::Result<TokenStream2> {
+    let level = match lit.value().to_lowercase().as_str() {
+        "error" => quote!(ERROR),
+        "warn" => quote!(WARN),
+        "info" => quote!(INFO),
+        "debug" => quote!(DEBUG),
+        "trace" => quote!(TRACE),
+        _ => return Err(syn::Error::new(lit.span(), "expected one of \"error\", \"warn\", \"info\", \"debug\", \"trace\""))
+    };
+    Ok(quote!(tracing::Level::#level))
+}
+
+/// Wraps the body of an `async fn` into `TracingTask`, same as writing
+/// `TracingTask::new(span!(...), async move { ... }).instrument().await` by hand
+///
+/// Function arguments are never recorded implicitly, list whatever is needed in `fields(...)`:
+/// `#[traced_task(fields(n, id = %self.id), short_lived, level = "debug")]`
+///
+/// Options:
+/// - `fields(...)` - span fields, anything `span!` accepts
+/// - `short_lived` - use `TracingTask::new_short_lived`
+/// - `log_panics` / `catch_panics` - see `TracingTask::log_panics` and `TracingTask::catch_panics`
+/// - `name = "..."` - span name and `name` field, by default the span is named after the full path and line of the
+///   function (methods of different types in one module would share the path otherwise) and the field holds the
+///   function rendered with the default `NameStyle`, same as `span!()`
+/// - `level = "..."` - span level, `ERROR` by default just like `span!`
+/// - `start_level`, `ok_level`, `err_level`, `cancel_level` = "..." - levels of task events
+#[proc_macro_attribute]
+pub fn traced_task(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut parsed = Args::default();
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("fields") {
+            let content;
+            syn::parenthesized!(content in meta.input);
+            parsed.fields = Some(content.parse()?);
+        } else if meta.path.is_ident("short_lived") {
+            parsed.short_lived = true;
+        } else if meta.path.is_ident("log_panics") {
+            parsed.log_panics = true;
+        } else if meta.path.is_ident("catch_panics") {
+            parsed.catch_panics = true;
+        } else if meta.path.is_ident("name") {
+            parsed.name = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("level") {
+            parsed.level = Some(parse_level(meta.value()?.parse()?)?);
+        } else if meta.path.is_ident("start_level") {
+            parsed.start_level = Some(parse_level(meta.value()?.parse()?)?);
+        } else if meta.path.is_ident("ok_level") {
+            parsed.ok_level = Some(parse_level(meta.value()?.parse()?)?);
+        } else if meta.path.is_ident("err_level") {
+            parsed.err_level = Some(parse_level(meta.value()?.parse()?)?);
+        } else if meta.path.is_ident("cancel_level") {
+            parsed.cancel_level = Some(parse_level(meta.value()?.parse()?)?);
+        } else {
+            return Err(meta.error("unsupported traced_task option"));
+        }
+        Ok(())
+    });
+    parse_macro_input!(args with parser);
+
+    let item = parse_macro_input!(item as ItemFn);
+    match expand(parsed, item) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into()
+    }
+}
+
+fn expand(args: Args, mut item: ItemFn) -> syn::Result<TokenStream2> {
+    if item.sig.asyncness.is_none() {
+        return Err(syn::Error::new(item.sig.fn_token.span(), "traced_task can only be applied to async fn"));
+    }
+
+    let level = args.level.unwrap_or_else(|| quote!(tracing::Level::ERROR));
+    let fields = args.fields.map(|fields| quote!(, #fields));
+    // the span gets named after the full path and line of the function unless an explicit name was given, an attribute
+    // can't see the impl block a method is in
+    let span = match args.name {
+        Some(name) => quote!(::tracing_tools::span!(#level, #name #fields)),
+        None => {
+            let name = LitStr::new(&item.sig.ident.to_string(), item.sig.ident.span());
+            let line = quote_spanned!(item.sig.ident.span()=> line!());
+            quote!(::tracing_tools::span!(@callsite concat!(module_path!(), "::", #name, ":", #line),
+                ::tracing_tools::default_name_style(), #level #fields))
+        }
+    };
+
+    let new = if args.short_lived {
+        quote!(new_short_lived)
+    } else {
+        quote!(new)
+    };
+    let mut builder = TokenStream2::new();
+    if let Some(level) = args.start_level {
+        builder.extend(quote!(.start_level(#level)));
+    }
+    if let Some(level) = args.ok_level {
+        builder.extend(quote!(.ok_level(#level)));
+    }
+    if let Some(level) = args.err_level {
+        builder.extend(quote!(.err_level(#level)));
+    }
+    if let Some(level) = args.cancel_level {
+        builder.extend(quote!(.cancel_level(#level)));
+    }
+    if args.log_panics {
+        builder.extend(quote!(.log_panics()));
+    }
+    if args.catch_panics {
+        builder.extend(quote!(.catch_panics()));
+    }
+
+    // pin the type of the inner async block to the declared return type, so `?` keeps working inside of it
+    let ret = match &item.sig.output {
+        ReturnType::Default => Some(quote!(())),
+        ReturnType::Type(_, ty) if !contains_impl_trait(ty) => Some(ty.to_token_stream()),
+        ReturnType::Type(..) => None
+    };
+    let body = &item.block;
+    let body = match ret {
+        Some(ret) => quote!({
+            let __traced_task_ret: #ret = #body;
+            __traced_task_ret
+        }),
+        None => quote!(#body)
+    };
+
+    item.block = syn::parse2(quote!({
+        ::tracing_tools::TracingTask::#new(#span, async move #body)#builder.instrument().await
+    }))?;
+    Ok(item.into_token_stream())
+}
+
+fn contains_impl_trait(ty: &Type) -> bool {
+    ty.to_token_stream().into_iter().any(|t| matches!(t, proc_macro2::TokenTree::Ident(ident) if ident == "impl"))
+}
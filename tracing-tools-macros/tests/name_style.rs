@@ -0,0 +1,49 @@
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use tracing::{Subscriber, field::{Field, Visit}, span::{Attributes, Id}};
+use tracing_subscriber::{layer::{Context, Layer}, prelude::*};
+use tracing_tools::{traced_task, NameStyle};
+
+#[derive(Clone, Default)]
+struct Names(Arc<Mutex<Vec<String>>>);
+
+impl Visit for Names {
+    fn record_debug(&mut self, _field: &Field, _value: &dyn Debug) {}
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "name" {
+            self.0.lock().unwrap().push(value.to_string());
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for Names {
+    fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+        attrs.record(&mut self.clone());
+    }
+}
+
+struct Pot;
+
+impl Pot {
+    #[traced_task]
+    async fn brew(&self) {}
+
+    #[traced_task(name = "pour")]
+    async fn pour(&self) {}
+}
+
+// a single test, the default style is global
+#[tokio::test]
+async fn traced_task_follows_the_default_name_style() {
+    let names = Names::default();
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(names.clone()));
+
+    Pot.brew().await;
+    tracing_tools::set_default_name_style(NameStyle::Full);
+    Pot.brew().await;
+    Pot.pour().await;
+
+    assert_eq!(*names.0.lock().unwrap(), ["Pot::brew", "name_style::Pot::brew", "pour"]);
+}
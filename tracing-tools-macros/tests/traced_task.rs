@@ -0,0 +1,61 @@
+use anyhow::{anyhow, Context};
+use tracing_tools::traced_task;
+
+type Result<T> = anyhow::Result<T>;
+
+struct Test {
+    id: usize
+}
+
+impl Test {
+    fn drink_coffee(&self) -> Result<()> {
+        Err(anyhow!("out of coffee"))
+    }
+
+    #[traced_task(fields(n, id = self.id))]
+    async fn add(&self, n: usize) -> Result<usize> {
+        Ok(self.id + n)
+    }
+
+    #[traced_task(fields(n), short_lived, level = "debug", err_level = "warn")]
+    async fn fail(&self, n: usize) -> Result<usize> {
+        self.drink_coffee().context("cannot drink coffee")?;
+        Ok(n)
+    }
+
+    #[traced_task(name = "consume")]
+    async fn consume(self, mut n: usize) -> Option<usize> {
+        n += self.id;
+        if n > 10 {
+            return None;
+        }
+        Some(n)
+    }
+}
+
+#[traced_task]
+async fn unit() {}
+
+#[tokio::test]
+async fn traced_task_keeps_fn_semantics() {
+    let t = Test { id: 1 };
+    assert_eq!(t.add(2).await.unwrap(), 3);
+    assert_eq!(format!("{:#}", t.fail(2).await.unwrap_err()), "cannot drink coffee: out of coffee");
+    assert_eq!(Test { id: 1 }.consume(2).await, Some(3));
+    assert_eq!(Test { id: 10 }.consume(2).await, None);
+    unit().await;
+}
+
+#[traced_task(catch_panics)]
+async fn explode(n: usize) -> Result<usize> {
+    if n > 0 {
+        panic!("boom");
+    }
+    Ok(n)
+}
+
+#[tokio::test]
+async fn traced_task_catches_panics() {
+    assert_eq!(explode(0).await.unwrap(), 0);
+    assert_eq!(explode(1).await.unwrap_err().to_string(), "task panicked: boom");
+}